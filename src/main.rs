@@ -5,15 +5,25 @@ use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
 use crossterm::terminal::{
     EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
 };
+use flate2::read::GzDecoder;
+use futures::stream::{FuturesUnordered, StreamExt};
+use once_cell::sync::Lazy;
 use ratatui::prelude::*;
 use ratatui::widgets::*;
 use ssh2::{FileStat, Session};
-use std::io::{Read, Write, stdout};
+use std::io::{Read, Seek, SeekFrom, Write, stdout};
 use std::net::TcpStream;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tokio::sync::mpsc;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+use tokio::sync::{Semaphore, mpsc};
+
+static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+static THEME_SET: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -21,12 +31,26 @@ struct Args {
     remote: String,
     #[arg(default_value = ".")]
     path: String,
+    /// Maximum number of files to download concurrently during a folder download.
+    #[arg(long, default_value_t = 4)]
+    max_concurrent: usize,
+    /// Skip the post-download md5 checksum comparison against the remote file.
+    #[arg(long)]
+    no_verify: bool,
+    /// Private key file to authenticate with, instead of the ssh-agent or a password.
+    #[arg(short = 'i', long)]
+    identity: Option<PathBuf>,
+    /// Download whole directories as a single remote `tar.gz` stream instead of file-by-file.
+    #[arg(long)]
+    archive: bool,
 }
 
 #[derive(Debug, Clone)]
 enum DownloadProgress {
     Started,
-    InProgress(u64, u64),
+    Resumed(u64),
+    InProgress(u64, u64, f64),
+    Verifying,
     Completed,
     Failed(String),
 }
@@ -38,13 +62,31 @@ enum FileListItem {
     Directory(String),
 }
 
+#[derive(Default)]
+struct DownloadTally {
+    pending: usize,
+    completed: usize,
+    failed: usize,
+}
+
+enum FilePreview {
+    Empty,
+    Loading,
+    Text(Vec<Line<'static>>),
+    Binary(FileStat),
+    Error(String),
+}
+
 struct AppState {
     exit: bool,
     current_path: PathBuf,
     items: Vec<FileListItem>,
     selected_item: ListState,
     logs: Vec<String>,
-    download_status: Option<(String, DownloadProgress)>,
+    downloads: Vec<(String, DownloadProgress)>,
+    download_tally: DownloadTally,
+    preview: FilePreview,
+    preview_generation: u64,
 }
 
 impl AppState {
@@ -55,13 +97,27 @@ impl AppState {
             items: Vec::new(),
             selected_item: ListState::default(),
             logs: vec!["App initialized".to_string()],
-            download_status: None,
+            downloads: Vec::new(),
+            download_tally: DownloadTally::default(),
+            preview: FilePreview::Empty,
+            preview_generation: 0,
         }
     }
 
     fn log(&mut self, message: &str) {
         self.logs.push(message.to_string());
     }
+
+    fn upsert_download(&mut self, name: String, progress: DownloadProgress) {
+        match self.downloads.iter_mut().find(|(n, _)| *n == name) {
+            Some(entry) => entry.1 = progress,
+            None => self.downloads.push((name, progress)),
+        }
+    }
+
+    fn finish_download(&mut self, name: &str) {
+        self.downloads.retain(|(n, _)| n != name);
+    }
 }
 
 struct App {
@@ -69,66 +125,81 @@ struct App {
     state: Arc<Mutex<AppState>>,
     progress_receiver: mpsc::Receiver<(String, DownloadProgress)>,
     progress_sender: mpsc::Sender<(String, DownloadProgress)>,
+    max_concurrent: usize,
+    verify: bool,
+    archive_mode: bool,
 }
 
 impl App {
-    fn new(sess: Session, initial_path: String) -> Self {
+    fn new(
+        sess: Session,
+        initial_path: String,
+        max_concurrent: usize,
+        verify: bool,
+        archive_mode: bool,
+    ) -> Self {
         let (tx, rx) = mpsc::channel(100);
         let mut app = Self {
             sess,
             state: Arc::new(Mutex::new(AppState::new(initial_path))),
             progress_receiver: rx,
             progress_sender: tx,
+            max_concurrent,
+            verify,
+            archive_mode,
         };
         app.refresh_files();
         app
     }
 
     fn refresh_files(&mut self) {
-        let mut state = self.state.lock().unwrap();
-        let path_display = state.current_path.display().to_string();
-        state.log(&format!("Fetching files from '{path_display}'..."));
-        let sftp = self.sess.sftp().unwrap();
-        match sftp.readdir(&state.current_path) {
-            Ok(sftp_items) => {
-                let mut items = Vec::new();
-                // Add parent directory link if not in root
-                if state.current_path != PathBuf::from(".")
-                    && state.current_path != PathBuf::from("/")
-                {
-                    items.push(FileListItem::Parent);
-                }
+        {
+            let mut state = self.state.lock().unwrap();
+            let path_display = state.current_path.display().to_string();
+            state.log(&format!("Fetching files from '{path_display}'..."));
+            let sftp = self.sess.sftp().unwrap();
+            match sftp.readdir(&state.current_path) {
+                Ok(sftp_items) => {
+                    let mut items = Vec::new();
+                    // Add parent directory link if not in root
+                    if state.current_path != PathBuf::from(".")
+                        && state.current_path != PathBuf::from("/")
+                    {
+                        items.push(FileListItem::Parent);
+                    }
 
-                let mut sorted_items: Vec<_> = sftp_items.into_iter().collect();
-                sorted_items.sort_by(|(path_a, _), (path_b, _)| {
-                    let a_is_dir = path_a.is_dir();
-                    let b_is_dir = path_b.is_dir();
-                    a_is_dir.cmp(&b_is_dir).reverse().then_with(|| {
-                        path_a
-                            .file_name()
-                            .unwrap_or_default()
-                            .cmp(path_b.file_name().unwrap_or_default())
-                    })
-                });
+                    let mut sorted_items: Vec<_> = sftp_items.into_iter().collect();
+                    sorted_items.sort_by(|(path_a, _), (path_b, _)| {
+                        let a_is_dir = path_a.is_dir();
+                        let b_is_dir = path_b.is_dir();
+                        a_is_dir.cmp(&b_is_dir).reverse().then_with(|| {
+                            path_a
+                                .file_name()
+                                .unwrap_or_default()
+                                .cmp(path_b.file_name().unwrap_or_default())
+                        })
+                    });
 
-                for (path, stat) in sorted_items {
-                    let name = path.file_name().unwrap().to_str().unwrap().to_string();
-                    if stat.is_dir() {
-                        items.push(FileListItem::Directory(name));
-                    } else {
-                        items.push(FileListItem::File(name, stat));
+                    for (path, stat) in sorted_items {
+                        let name = path.file_name().unwrap().to_str().unwrap().to_string();
+                        if stat.is_dir() {
+                            items.push(FileListItem::Directory(name));
+                        } else {
+                            items.push(FileListItem::File(name, stat));
+                        }
                     }
-                }
-                state.items = items;
+                    state.items = items;
 
-                if !state.items.is_empty() {
-                    state.selected_item.select(Some(0));
+                    if !state.items.is_empty() {
+                        state.selected_item.select(Some(0));
+                    }
+                    let count = state.items.len();
+                    state.log(&format!("Found {count} items."));
                 }
-                let count = state.items.len();
-                state.log(&format!("Found {count} items."));
+                Err(e) => state.log(&format!("Error fetching files: {e}")),
             }
-            Err(e) => state.log(&format!("Error fetching files: {e}")),
         }
+        self.on_selection_changed();
     }
 
     fn on_key(&mut self, key: KeyEvent) {
@@ -149,39 +220,92 @@ impl App {
     }
 
     fn select_previous(&self) {
-        let mut state = self.state.lock().unwrap();
-        if state.items.is_empty() {
-            return;
-        }
-        let i = match state.selected_item.selected() {
-            Some(i) => {
-                if i == 0 {
-                    state.items.len() - 1
-                } else {
-                    i - 1
-                }
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.items.is_empty() {
+                return;
             }
-            None => 0,
-        };
-        state.selected_item.select(Some(i));
+            let i = match state.selected_item.selected() {
+                Some(i) => {
+                    if i == 0 {
+                        state.items.len() - 1
+                    } else {
+                        i - 1
+                    }
+                }
+                None => 0,
+            };
+            state.selected_item.select(Some(i));
+        }
+        self.on_selection_changed();
     }
 
     fn select_next(&self) {
-        let mut state = self.state.lock().unwrap();
-        if state.items.is_empty() {
-            return;
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.items.is_empty() {
+                return;
+            }
+            let i = match state.selected_item.selected() {
+                Some(i) => {
+                    if i >= state.items.len() - 1 {
+                        0
+                    } else {
+                        i + 1
+                    }
+                }
+                None => 0,
+            };
+            state.selected_item.select(Some(i));
         }
-        let i = match state.selected_item.selected() {
-            Some(i) => {
-                if i >= state.items.len() - 1 {
-                    0
-                } else {
-                    i + 1
+        self.on_selection_changed();
+    }
+
+    // Debounced: bumps preview_generation so a stale in-flight fetch discards itself.
+    fn on_selection_changed(&self) {
+        let (generation, selected, current_path) = {
+            let mut state = self.state.lock().unwrap();
+            state.preview_generation = state.preview_generation.wrapping_add(1);
+            let selected = state
+                .selected_item
+                .selected()
+                .and_then(|i| state.items.get(i).cloned());
+            state.preview = FilePreview::Empty;
+            (
+                state.preview_generation,
+                selected,
+                state.current_path.clone(),
+            )
+        };
+
+        let Some(FileListItem::File(name, stat)) = selected else {
+            return;
+        };
+
+        let sess = self.sess.clone();
+        let state_handle = self.state.clone();
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            {
+                let mut state = state_handle.lock().unwrap();
+                if state.preview_generation != generation {
+                    return;
                 }
+                state.preview = FilePreview::Loading;
             }
-            None => 0,
-        };
-        state.selected_item.select(Some(i));
+
+            let remote_path = current_path.join(&name);
+            let preview = match fetch_preview(&sess, &remote_path, &name, &stat) {
+                Ok(preview) => preview,
+                Err(e) => FilePreview::Error(e.to_string()),
+            };
+
+            let mut state = state_handle.lock().unwrap();
+            if state.preview_generation == generation {
+                state.preview = preview;
+            }
+        });
     }
 
     fn handle_enter(&mut self, modifiers: KeyModifiers) {
@@ -215,10 +339,11 @@ impl App {
             FileListItem::File(name, stat) => {
                 let mut state = self.state.lock().unwrap();
                 state.log(&format!("Starting download for '{name}'"));
-                state.download_status = Some((name.clone(), DownloadProgress::Started));
+                state.upsert_download(name.clone(), DownloadProgress::Started);
                 let sess_clone = self.sess.clone();
                 let remote_path = state.current_path.join(&name);
                 let progress_sender_clone = self.progress_sender.clone();
+                let verify = self.verify;
 
                 tokio::spawn(async move {
                     let result = download_file(
@@ -227,6 +352,7 @@ impl App {
                         name.clone(),
                         stat,
                         progress_sender_clone.clone(),
+                        verify,
                     )
                     .await;
 
@@ -248,38 +374,43 @@ impl App {
         let progress_sender_clone = self.progress_sender.clone();
         let app_state_clone = self.state.clone();
         let name_owned = name.to_string();
+        let max_concurrent = self.max_concurrent;
+        let verify = self.verify;
+        let archive_mode = self.archive_mode;
 
         drop(state);
 
         tokio::spawn(async move {
-            let remote_path = current_path.join(name_owned);
-            let files_to_download =
-                match find_files_recursive(sess_clone.clone(), remote_path.clone()).await {
-                    Ok(files) => files,
+            if archive_mode {
+                match download_and_report_archive(
+                    sess_clone.clone(),
+                    app_state_clone.clone(),
+                    progress_sender_clone.clone(),
+                    current_path.clone(),
+                    name_owned.clone(),
+                )
+                .await
+                {
+                    Ok(()) => return,
                     Err(e) => {
                         let mut state = app_state_clone.lock().unwrap();
-                        state.log(&format!("Error finding files in directory: {e}"));
-                        return;
+                        state.log(&format!(
+                            "Archive download failed ({e}), falling back to per-file download"
+                        ));
                     }
-                };
-            {
-                let mut state = app_state_clone.lock().unwrap();
-                state.log(&format!(
-                    "Found {} files to download.",
-                    files_to_download.len()
-                ));
+                }
             }
 
-            for (file_path, file_stat) in files_to_download {
-                tokio::spawn(download_and_report_progress(
-                    sess_clone.clone(),
-                    app_state_clone.clone(),
-                    progress_sender_clone.clone(),
-                    file_path,
-                    file_stat,
-                ));
-                tokio::time::sleep(Duration::from_millis(100)).await;
-            }
+            download_folder_per_file(
+                sess_clone,
+                app_state_clone,
+                progress_sender_clone,
+                current_path,
+                name_owned,
+                max_concurrent,
+                verify,
+            )
+            .await;
         });
     }
 
@@ -289,14 +420,23 @@ impl App {
             match progress {
                 DownloadProgress::Completed => {
                     state.log(&format!("Download complete: {name}"));
-                    state.download_status = None;
+                    state.finish_download(&name);
+                    state.download_tally.completed += 1;
                 }
                 DownloadProgress::Failed(e) => {
                     state.log(&format!("Download failed for {name}: {e}"));
-                    state.download_status = None;
+                    state.finish_download(&name);
+                    state.download_tally.failed += 1;
+                }
+                DownloadProgress::Resumed(skipped) => {
+                    state.log(&format!(
+                        "Resuming '{name}' from {} already downloaded",
+                        humansize::format_size(skipped, humansize::BINARY)
+                    ));
+                    state.upsert_download(name, progress);
                 }
                 _ => {
-                    state.download_status = Some((name, progress));
+                    state.upsert_download(name, progress);
                 }
             }
         }
@@ -306,36 +446,133 @@ impl App {
 fn find_files_recursive<'a>(
     sess: Session,
     path: PathBuf,
-) -> futures::future::BoxFuture<'a, Result<Vec<(PathBuf, FileStat)>>> {
+) -> futures::future::BoxFuture<'a, Result<(Vec<(PathBuf, FileStat)>, Vec<PathBuf>)>> {
     Box::pin(async move {
         let sftp = sess.sftp()?;
         let mut files = Vec::new();
+        let mut dirs = Vec::new();
         let readdir_result = sftp.readdir(&path)?;
 
         for (item_path, stat) in readdir_result {
             if stat.is_dir() {
-                let mut sub_files = find_files_recursive(sess.clone(), item_path).await?;
+                dirs.push(item_path.clone());
+                let (mut sub_files, mut sub_dirs) =
+                    find_files_recursive(sess.clone(), item_path).await?;
                 files.append(&mut sub_files);
+                dirs.append(&mut sub_dirs);
             } else {
                 files.push((item_path, stat));
             }
         }
-        Ok(files)
+        Ok((files, dirs))
     })
 }
 
+/// The original per-file folder download: walk the tree, then fan the files
+/// out through a semaphore-gated queue.
+async fn download_folder_per_file(
+    sess: Session,
+    app_state: Arc<Mutex<AppState>>,
+    progress_sender: mpsc::Sender<(String, DownloadProgress)>,
+    current_path: PathBuf,
+    dir_name: String,
+    max_concurrent: usize,
+    verify: bool,
+) {
+    let remote_path = current_path.join(&dir_name);
+    let (files_to_download, dirs_found) =
+        match find_files_recursive(sess.clone(), remote_path.clone()).await {
+            Ok(result) => result,
+            Err(e) => {
+                let mut state = app_state.lock().unwrap();
+                state.log(&format!("Error finding files in directory: {e}"));
+                return;
+            }
+        };
+    {
+        let mut state = app_state.lock().unwrap();
+        state.log(&format!(
+            "Found {} files to download.",
+            files_to_download.len()
+        ));
+        state.download_tally.pending += files_to_download.len();
+    }
+
+    // Mirror the remote subdirectory under `dir_name` locally, the same way
+    // the archive path unpacks its tarball, including directories that have
+    // no files of their own, so both download modes leave the same tree.
+    if let Err(e) = std::fs::create_dir_all(&dir_name) {
+        let mut state = app_state.lock().unwrap();
+        state.log(&format!("Error creating directory '{dir_name}': {e}"));
+        return;
+    }
+    for dir in &dirs_found {
+        let relative = dir.strip_prefix(&remote_path).unwrap_or(dir);
+        let _ = std::fs::create_dir_all(PathBuf::from(&dir_name).join(relative));
+    }
+
+    let semaphore = Arc::new(Semaphore::new(max_concurrent));
+    let mut jobs = FuturesUnordered::new();
+
+    for (file_path, file_stat) in files_to_download {
+        let semaphore = semaphore.clone();
+        let sess = sess.clone();
+        let app_state = app_state.clone();
+        let progress_sender = progress_sender.clone();
+        let relative = file_path
+            .strip_prefix(&remote_path)
+            .unwrap_or(&file_path)
+            .to_path_buf();
+        let local_path = PathBuf::from(&dir_name).join(relative);
+
+        jobs.push(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("semaphore is never closed");
+            download_and_report_progress(
+                sess,
+                app_state,
+                progress_sender,
+                file_path,
+                local_path,
+                file_stat,
+                verify,
+            )
+            .await;
+        });
+    }
+
+    while jobs.next().await.is_some() {}
+}
+
 async fn download_and_report_progress(
     sess: Session,
     app_state: Arc<Mutex<AppState>>,
     progress_sender: mpsc::Sender<(String, DownloadProgress)>,
     file_path: PathBuf,
+    local_path: PathBuf,
     file_stat: FileStat,
+    verify: bool,
 ) {
-    let local_filename = file_path.file_name().unwrap().to_str().unwrap().to_string();
+    let local_filename = local_path.to_string_lossy().to_string();
+    if let Some(parent) = local_path.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            let mut state = app_state.lock().unwrap();
+            state.log(&format!(
+                "Error creating directory for '{local_filename}': {e}"
+            ));
+            let _ = progress_sender
+                .send((local_filename, DownloadProgress::Failed(e.to_string())))
+                .await;
+            return;
+        }
+    }
     {
         let mut state = app_state.lock().unwrap();
         state.log(&format!("Starting download for '{local_filename}'"));
-        state.download_status = Some((local_filename.clone(), DownloadProgress::Started));
+        state.download_tally.pending = state.download_tally.pending.saturating_sub(1);
+        state.upsert_download(local_filename.clone(), DownloadProgress::Started);
     }
 
     let download_result = download_file(
@@ -344,6 +581,7 @@ async fn download_and_report_progress(
         local_filename.clone(),
         file_stat,
         progress_sender.clone(),
+        verify,
     )
     .await;
 
@@ -357,41 +595,367 @@ async fn download_and_report_progress(
     }
 }
 
+// Keyed on the full remote path rather than the bare filename, so two files
+// with the same basename in different remote directories don't share (and
+// corrupt) the same resumable temp file.
+fn tmp_download_path(remote_path: &Path) -> PathBuf {
+    let key = remote_path.display().to_string().replace(['/', '\\'], "_");
+    PathBuf::from(format!("tmp-{key}"))
+}
+
+async fn download_and_report_archive(
+    sess: Session,
+    app_state: Arc<Mutex<AppState>>,
+    progress_sender: mpsc::Sender<(String, DownloadProgress)>,
+    current_path: PathBuf,
+    dir_name: String,
+) -> Result<()> {
+    let job_name = format!("{dir_name}.tar.gz");
+    {
+        let mut state = app_state.lock().unwrap();
+        state.log(&format!("Starting archive download for '{dir_name}'"));
+        state.upsert_download(job_name.clone(), DownloadProgress::Started);
+    }
+
+    let result = download_archive(
+        sess,
+        current_path,
+        dir_name,
+        progress_sender.clone(),
+        job_name.clone(),
+    )
+    .await;
+
+    if let Err(e) = &result {
+        let _ = progress_sender
+            .send((job_name, DownloadProgress::Failed(e.to_string())))
+            .await;
+    }
+    result
+}
+
+// Single-quotes `s` for a remote shell command, escaping embedded quotes.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+async fn download_archive(
+    sess: Session,
+    parent_path: PathBuf,
+    dir_name: String,
+    sender: mpsc::Sender<(String, DownloadProgress)>,
+    job_name: String,
+) -> Result<()> {
+    let parent_display = parent_path.display().to_string();
+    let mut channel = sess.channel_session()?;
+    channel.exec(&format!(
+        "tar -czf - -C {} {}",
+        shell_quote(&parent_display),
+        shell_quote(&dir_name)
+    ))?;
+
+    let tmp_path = tmp_download_path(Path::new(&job_name));
+    let mut local_file = std::fs::File::create(&tmp_path)?;
+    let mut buffer = [0u8; 8192];
+    let mut downloaded_bytes = 0u64;
+
+    loop {
+        let bytes_read = channel.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        local_file.write_all(&buffer[..bytes_read])?;
+        downloaded_bytes += bytes_read as u64;
+        sender
+            .send((
+                job_name.clone(),
+                DownloadProgress::InProgress(downloaded_bytes, 0, 0.0),
+            ))
+            .await?;
+    }
+    local_file.flush()?;
+
+    channel.send_eof()?;
+    channel.wait_eof()?;
+    channel.close()?;
+    channel.wait_close()?;
+
+    if channel.exit_status()? != 0 {
+        let _ = std::fs::remove_file(&tmp_path);
+        eyre::bail!("remote tar command exited with a non-zero status (is `tar` installed?)");
+    }
+
+    let tar_gz = std::fs::File::open(&tmp_path)?;
+    tar::Archive::new(GzDecoder::new(tar_gz)).unpack(".")?;
+    std::fs::remove_file(&tmp_path)?;
+
+    sender
+        .send((job_name, DownloadProgress::Completed))
+        .await?;
+    Ok(())
+}
+
+const PREVIEW_SAMPLE_BYTES: usize = 64 * 1024;
+
+/// Fetches a small prefix of the remote file and turns it into a `FilePreview`,
+/// syntax-highlighting text files and summarizing binary ones.
+fn fetch_preview(
+    sess: &Session,
+    remote_path: &Path,
+    name: &str,
+    stat: &FileStat,
+) -> Result<FilePreview> {
+    let sftp = sess.sftp()?;
+    let mut file = sftp.open(remote_path)?;
+
+    let mut buffer = vec![0u8; PREVIEW_SAMPLE_BYTES];
+    let mut total_read = 0;
+    while total_read < buffer.len() {
+        let bytes_read = file.read(&mut buffer[total_read..])?;
+        if bytes_read == 0 {
+            break;
+        }
+        total_read += bytes_read;
+    }
+    buffer.truncate(total_read);
+
+    if buffer.contains(&0) {
+        return Ok(FilePreview::Binary(stat.clone()));
+    }
+
+    let text = String::from_utf8_lossy(&buffer);
+    Ok(FilePreview::Text(highlight_text(name, &text)))
+}
+
+fn highlight_text(filename: &str, content: &str) -> Vec<Line<'static>> {
+    let syntax = Path::new(filename)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(|ext| SYNTAX_SET.find_syntax_by_extension(ext))
+        .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text());
+    let theme = &THEME_SET.themes["base16-ocean.dark"];
+    let mut highlighter = HighlightLines::new(syntax, theme);
+
+    LinesWithEndings::from(content)
+        .map(|line| {
+            let ranges = highlighter
+                .highlight_line(line, &SYNTAX_SET)
+                .unwrap_or_default();
+            let spans: Vec<Span<'static>> = ranges
+                .into_iter()
+                .map(|(style, text)| {
+                    let color = Color::Rgb(
+                        style.foreground.r,
+                        style.foreground.g,
+                        style.foreground.b,
+                    );
+                    Span::styled(
+                        text.trim_end_matches(['\n', '\r']).to_string(),
+                        Style::default().fg(color),
+                    )
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+fn format_permissions(mode: u32) -> String {
+    const BITS: [(u32, char); 9] = [
+        (0o400, 'r'),
+        (0o200, 'w'),
+        (0o100, 'x'),
+        (0o040, 'r'),
+        (0o020, 'w'),
+        (0o010, 'x'),
+        (0o004, 'r'),
+        (0o002, 'w'),
+        (0o001, 'x'),
+    ];
+    BITS.iter()
+        .map(|(bit, ch)| if mode & bit != 0 { *ch } else { '-' })
+        .collect()
+}
+
+fn remote_md5(sess: &Session, remote_path: &Path) -> Result<String> {
+    let remote_path = remote_path.display().to_string();
+    let mut channel = sess.channel_session()?;
+    channel.exec(&format!("md5sum {}", shell_quote(&remote_path)))?;
+    let mut output = String::new();
+    channel.read_to_string(&mut output)?;
+    channel.wait_close()?;
+    output
+        .split_whitespace()
+        .next()
+        .map(|digest| digest.to_string())
+        .ok_or_else(|| eyre::eyre!("md5sum produced no output for '{remote_path}'"))
+}
+
 async fn download_file(
     sess: Session,
     remote_path: PathBuf,
     local_filename: String,
     stat: FileStat,
     sender: mpsc::Sender<(String, DownloadProgress)>,
+    verify: bool,
 ) -> Result<()> {
     let sftp = sess.sftp()?;
     let mut remote_file = sftp.open(&remote_path)?;
-    let mut local_file = std::fs::File::create(&local_filename)?;
     let total_bytes = stat.size.unwrap_or(0);
-    let mut downloaded_bytes = 0;
+    let tmp_path = tmp_download_path(&remote_path);
+
+    // Resume from a partial temp file left behind by an interrupted transfer,
+    // unless the remote has since shrunk below what we already have locally.
+    let mut downloaded_bytes = match std::fs::metadata(&tmp_path) {
+        Ok(meta) if meta.len() <= total_bytes => meta.len(),
+        _ => 0,
+    };
+
+    let mut md5_ctx = md5::Context::new();
+
+    let mut local_file = if downloaded_bytes > 0 {
+        if verify {
+            let mut existing = std::fs::File::open(&tmp_path)?;
+            let mut buf = [0u8; 8192];
+            loop {
+                let n = existing.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                md5_ctx.consume(&buf[..n]);
+            }
+        }
+        remote_file.seek(SeekFrom::Start(downloaded_bytes))?;
+        sender
+            .send((
+                local_filename.clone(),
+                DownloadProgress::Resumed(downloaded_bytes),
+            ))
+            .await?;
+        std::fs::OpenOptions::new().append(true).open(&tmp_path)?
+    } else {
+        std::fs::File::create(&tmp_path)?
+    };
+
     let mut buffer = [0; 8192];
 
+    // Smoothed (EMA) throughput estimate, refreshed once per chunk so the
+    // gauge doesn't jitter with every 8 KiB read.
+    const SPEED_EMA_ALPHA: f64 = 0.3;
+    let mut speed = 0.0f64;
+    let mut last_instant = std::time::Instant::now();
+    let mut last_bytes = downloaded_bytes;
+
     loop {
         let bytes_read = remote_file.read(&mut buffer)?;
         if bytes_read == 0 {
             break;
         }
         local_file.write_all(&buffer[..bytes_read])?;
+        if verify {
+            md5_ctx.consume(&buffer[..bytes_read]);
+        }
         downloaded_bytes += bytes_read as u64;
+
+        let now = std::time::Instant::now();
+        let elapsed = now.duration_since(last_instant).as_secs_f64();
+        if elapsed > 0.0 {
+            let instantaneous = (downloaded_bytes - last_bytes) as f64 / elapsed;
+            speed = if speed == 0.0 {
+                instantaneous
+            } else {
+                SPEED_EMA_ALPHA * instantaneous + (1.0 - SPEED_EMA_ALPHA) * speed
+            };
+            last_instant = now;
+            last_bytes = downloaded_bytes;
+        }
+
         sender
             .send((
                 local_filename.clone(),
-                DownloadProgress::InProgress(downloaded_bytes, total_bytes),
+                DownloadProgress::InProgress(downloaded_bytes, total_bytes, speed),
             ))
             .await?;
     }
 
+    local_file.flush()?;
+
+    if verify {
+        sender
+            .send((local_filename.clone(), DownloadProgress::Verifying))
+            .await?;
+        let local_digest = format!("{:x}", md5_ctx.compute());
+        let remote_digest = remote_md5(&sess, &remote_path)?;
+        if local_digest != remote_digest {
+            let _ = std::fs::remove_file(&tmp_path);
+            eyre::bail!(
+                "checksum mismatch for '{local_filename}': local {local_digest}, remote {remote_digest}"
+            );
+        }
+    }
+
+    std::fs::rename(&tmp_path, &local_filename)?;
+
     sender
         .send((local_filename.clone(), DownloadProgress::Completed))
         .await?;
     Ok(())
 }
 
+fn keyring_entry(user: &str, host: &str) -> Result<keyring::Entry> {
+    Ok(keyring::Entry::new("vsftp", &format!("{user}@{host}"))?)
+}
+
+// Tries, in order: identity file, ssh-agent, saved keyring password, interactive prompt.
+// A given identity file that is rejected is returned as an error; the later steps are not tried.
+fn authenticate(sess: &Session, user: &str, host: &str, identity: Option<&Path>) -> Result<()> {
+    if let Some(identity_path) = identity {
+        sess.userauth_pubkey_file(user, None, identity_path, None)?;
+        if sess.authenticated() {
+            return Ok(());
+        }
+    }
+
+    if sess.userauth_agent(user).is_ok() && sess.authenticated() {
+        return Ok(());
+    }
+
+    let entry = match keyring_entry(user, host) {
+        Ok(entry) => {
+            if let Ok(saved_password) = entry.get_password() {
+                if sess.userauth_password(user, &saved_password).is_ok() && sess.authenticated() {
+                    return Ok(());
+                }
+            }
+            Some(entry)
+        }
+        Err(e) => {
+            eprintln!("Keyring unavailable, skipping saved-password lookup: {e}");
+            None
+        }
+    };
+
+    let password = rpassword::prompt_password(format!("Password for {user}@{host}: "))?;
+    sess.userauth_password(user, &password)?;
+    if !sess.authenticated() {
+        eyre::bail!("Authentication failed.");
+    }
+
+    if let Some(entry) = entry {
+        print!("Save this password in the OS keyring for future connections? [y/N]: ");
+        stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if answer.trim().eq_ignore_ascii_case("y") {
+            if let Err(e) = entry.set_password(&password) {
+                eprintln!("Failed to save password to keyring: {e}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     color_eyre::install()?;
@@ -403,17 +967,19 @@ async fn main() -> Result<()> {
     sess.set_tcp_stream(tcp);
     sess.handshake()?;
 
-    let password = rpassword::prompt_password(format!("Password for {user}: "))?;
-    sess.userauth_password(user, &password)?;
-    if !sess.authenticated() {
-        eyre::bail!("Authentication failed.");
-    }
+    authenticate(&sess, user, host, args.identity.as_deref())?;
 
     stdout().execute(EnterAlternateScreen)?;
     enable_raw_mode()?;
     let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
 
-    let mut app = App::new(sess, args.path);
+    let mut app = App::new(
+        sess,
+        args.path,
+        args.max_concurrent,
+        !args.no_verify,
+        args.archive,
+    );
 
     loop {
         {
@@ -439,12 +1005,17 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+fn format_eta(total: u64, downloaded: u64, speed: f64) -> String {
+    if speed <= 0.0 || downloaded >= total {
+        return "--:--".to_string();
+    }
+    let secs_remaining = ((total - downloaded) as f64 / speed).round() as u64;
+    format!("{:02}:{:02}", secs_remaining / 60, secs_remaining % 60)
+}
+
 fn ui(frame: &mut Frame, state: &mut AppState) {
-    // let (log_size, progress_size) = match state.download_status {
-    //     Some(_) => (7, 3),
-    //     None => (10, 0),
-    // };
-    let (log_size, progress_size) = (7, 3);
+    let log_size = 7;
+    let progress_size = (state.downloads.len() as u16 + 2).max(3);
 
     let main_layout = Layout::default()
         .direction(Direction::Vertical)
@@ -456,6 +1027,11 @@ fn ui(frame: &mut Frame, state: &mut AppState) {
         ])
         .split(frame.area());
 
+    let top_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(main_layout[0]);
+
     let items: Vec<ListItem> = state
         .items
         .iter()
@@ -478,7 +1054,37 @@ fn ui(frame: &mut Frame, state: &mut AppState) {
         )
         .highlight_symbol("> ");
 
-    frame.render_stateful_widget(file_list, main_layout[0], &mut state.selected_item);
+    frame.render_stateful_widget(file_list, top_layout[0], &mut state.selected_item);
+
+    let preview_lines: Vec<Line> = match &state.preview {
+        FilePreview::Empty => vec![Line::from("Select a file to preview it.")],
+        FilePreview::Loading => vec![Line::from("Loading preview...")],
+        FilePreview::Error(e) => vec![Line::from(format!("Preview error: {e}"))],
+        FilePreview::Text(lines) => lines.clone(),
+        FilePreview::Binary(stat) => vec![
+            Line::from("Binary file"),
+            Line::from(format!(
+                "Size: {}",
+                humansize::format_size(stat.size.unwrap_or(0), humansize::BINARY)
+            )),
+            Line::from(format!(
+                "Permissions: {}",
+                stat.perm
+                    .map(format_permissions)
+                    .unwrap_or_else(|| "unknown".to_string())
+            )),
+            Line::from(format!(
+                "Modified: {}",
+                stat.mtime
+                    .map(|secs| format!("{secs} (unix time)"))
+                    .unwrap_or_else(|| "unknown".to_string())
+            )),
+        ],
+    };
+    let preview_widget = Paragraph::new(preview_lines)
+        .block(Block::default().borders(Borders::ALL).title("Preview"))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(preview_widget, top_layout[1]);
 
     let log_messages: Vec<Line> = state
         .logs
@@ -493,35 +1099,53 @@ fn ui(frame: &mut Frame, state: &mut AppState) {
         .wrap(Wrap { trim: false });
     frame.render_widget(log_widget, main_layout[1]);
 
-    if let Some((name, progress)) = &state.download_status {
-        let (label, ratio) = match progress {
-            DownloadProgress::InProgress(downloaded, total) => (
-                format!(
-                    "Downloading '{}' {}/{}...",
-                    name,
-                    humansize::format_size(*downloaded, humansize::BINARY),
-                    humansize::format_size(*total, humansize::BINARY)
-                ),
-                if *total > 0 {
-                    *downloaded as f64 / *total as f64
-                } else {
-                    0.0
-                },
-            ),
-            _ => (String::new(), 0.0),
-        };
-
-        let gauge = Gauge::default()
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title("Download Progress"),
-            )
-            .gauge_style(Style::default().fg(Color::Green))
-            .ratio(ratio)
-            .label(label);
-        frame.render_widget(gauge, main_layout[2]);
-    }
+    let tally = &state.download_tally;
+    let progress_title = format!(
+        "Downloads (active: {}, pending: {}, completed: {}, failed: {})",
+        state.downloads.len(),
+        tally.pending,
+        tally.completed,
+        tally.failed
+    );
+    let progress_lines: Vec<Line> = if state.downloads.is_empty() {
+        vec![Line::from("No active transfers")]
+    } else {
+        state
+            .downloads
+            .iter()
+            .map(|(name, progress)| {
+                Line::from(match progress {
+                    DownloadProgress::Started => format!("{name}: starting..."),
+                    DownloadProgress::Resumed(skipped) => format!(
+                        "{name}: resuming from {}",
+                        humansize::format_size(*skipped, humansize::BINARY)
+                    ),
+                    DownloadProgress::InProgress(downloaded, total, speed) if *total > 0 => {
+                        format!(
+                            "{}: {}/{} ({}/s, ETA {})",
+                            name,
+                            humansize::format_size(*downloaded, humansize::BINARY),
+                            humansize::format_size(*total, humansize::BINARY),
+                            humansize::format_size(*speed as u64, humansize::BINARY),
+                            format_eta(*total, *downloaded, *speed),
+                        )
+                    }
+                    // Total size is unknown for streamed archive downloads.
+                    DownloadProgress::InProgress(downloaded, _, _) => format!(
+                        "{}: {} downloaded",
+                        name,
+                        humansize::format_size(*downloaded, humansize::BINARY)
+                    ),
+                    DownloadProgress::Verifying => format!("{name}: verifying checksum..."),
+                    DownloadProgress::Completed | DownloadProgress::Failed(_) => name.clone(),
+                })
+            })
+            .collect()
+    };
+    let progress_widget = Paragraph::new(progress_lines)
+        .block(Block::default().borders(Borders::ALL).title(progress_title))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(progress_widget, main_layout[2]);
 
     let status_bar = Paragraph::new(Line::from(format!(
         "Path: {}",